@@ -0,0 +1,186 @@
+//! Prometheus metric definitions exposed on `/metrics`.
+//!
+//! Known gap: [`MEILISEARCH_TASK_LATENCY_SECONDS`] and
+//! [`MEILISEARCH_EMBEDDER_REQUESTS`] are not observed or incremented anywhere —
+//! their call sites live in the index-scheduler and milli/embedders crates,
+//! which this source tree doesn't include. Since `lazy_static` registers a metric
+//! on first access, neither is ever registered, and neither appears on
+//! `/metrics`; don't treat them as fully delivered.
+
+use lazy_static::lazy_static;
+use prometheus::{
+    opts, register_gauge, register_histogram_vec, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, Gauge, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+};
+
+/// Name of the env var used to override [`DEFAULT_LATENCY_BUCKETS`] for the search
+/// and task latency histograms, as a comma-separated list of bucket upper bounds
+/// in seconds (e.g. `MEILI_METRICS_LATENCY_BUCKETS=0.01,0.1,1,10`).
+pub const LATENCY_BUCKETS_ENV_VAR: &str = "MEILI_METRICS_LATENCY_BUCKETS";
+
+/// Default latency buckets, in seconds, used for the search and task histograms
+/// when [`LATENCY_BUCKETS_ENV_VAR`] is unset or fails to parse. Tuned for
+/// sub-millisecond to multi-second requests.
+pub const DEFAULT_LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Reads [`LATENCY_BUCKETS_ENV_VAR`] and parses it into histogram buckets, falling
+/// back to [`DEFAULT_LATENCY_BUCKETS`] when the variable is unset, unparsable, or
+/// not strictly increasing (the `prometheus` crate rejects non-increasing buckets,
+/// and these statics are built with `.expect`, so letting a bad value through would
+/// panic the process on first access instead of just mis-behaving).
+fn latency_buckets() -> Vec<f64> {
+    std::env::var(LATENCY_BUCKETS_ENV_VAR)
+        .ok()
+        .and_then(|value| {
+            value.split(',').map(|bucket| bucket.trim().parse::<f64>()).collect::<Result<Vec<_>, _>>().ok()
+        })
+        .filter(|buckets| !buckets.is_empty() && is_strictly_increasing(buckets))
+        .unwrap_or_else(|| DEFAULT_LATENCY_BUCKETS.to_vec())
+}
+
+/// Whether `buckets` is sorted in strictly increasing order, as the `prometheus`
+/// crate requires for histogram bucket boundaries.
+fn is_strictly_increasing(buckets: &[f64]) -> bool {
+    buckets.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+/// Names of the `IntCounterVec` metrics registered below, used by the `/metrics`
+/// route to append the OpenMetrics-mandated `_total` suffix when a client
+/// negotiates the OpenMetrics exposition format.
+pub const COUNTER_METRIC_NAMES: &[&str] = &[
+    "meilisearch_http_responses",
+    "meilisearch_embedder_requests",
+    "meilisearch_degraded_search_requests",
+];
+
+/// Names of the instance-wide gauges that are only meaningful, and only
+/// disclosed, to a key authorized for every index. The `/metrics` route strips
+/// these out of the exposition for a key restricted to a subset of indexes,
+/// since they are process-global and would otherwise keep reporting whatever a
+/// previous, fully-authorized scrape last set them to.
+pub const GLOBAL_ONLY_METRIC_NAMES: &[&str] = &[
+    "meilisearch_db_size_bytes",
+    "meilisearch_used_db_size_bytes",
+    "meilisearch_index_count",
+    "meilisearch_search_queue_size",
+    "meilisearch_searches_running",
+    "meilisearch_searches_waiting_to_be_processed",
+    "meilisearch_last_update",
+    "meilisearch_is_indexing",
+    "meilisearch_task_queue_latency_seconds",
+    // `get_stats` aggregates task counts instance-wide with no per-index
+    // breakdown, so there's no way to scope this to a key's authorized indexes.
+    // Omit it for a restricted key rather than leaking every tenant's task
+    // counts to it.
+    "meilisearch_nb_tasks",
+];
+
+lazy_static! {
+    pub static ref MEILISEARCH_DB_SIZE_BYTES: IntGauge =
+        register_int_gauge!(opts!("meilisearch_db_size_bytes", "Meilisearch DB Size In Bytes"))
+            .expect("Can't create a metric");
+    pub static ref MEILISEARCH_USED_DB_SIZE_BYTES: IntGauge = register_int_gauge!(opts!(
+        "meilisearch_used_db_size_bytes",
+        "Meilisearch Used DB Size In Bytes"
+    ))
+    .expect("Can't create a metric");
+    pub static ref MEILISEARCH_INDEX_COUNT: IntGauge =
+        register_int_gauge!(opts!("meilisearch_index_count", "Meilisearch Index Count"))
+            .expect("Can't create a metric");
+    pub static ref MEILISEARCH_INDEX_DOCS_COUNT: IntGaugeVec = register_int_gauge_vec!(
+        opts!("meilisearch_index_docs_count", "Meilisearch Index Docs Count"),
+        &["index"]
+    )
+    .expect("Can't create a metric");
+    pub static ref MEILISEARCH_NB_TASKS: IntGaugeVec = register_int_gauge_vec!(
+        opts!("meilisearch_nb_tasks", "Meilisearch Number of Tasks"),
+        &["kind", "value"]
+    )
+    .expect("Can't create a metric");
+    pub static ref MEILISEARCH_LAST_UPDATE: IntGauge =
+        register_int_gauge!(opts!("meilisearch_last_update", "Meilisearch Last Update"))
+            .expect("Can't create a metric");
+    pub static ref MEILISEARCH_IS_INDEXING: IntGauge =
+        register_int_gauge!(opts!("meilisearch_is_indexing", "Meilisearch Is Indexing"))
+            .expect("Can't create a metric");
+    pub static ref MEILISEARCH_SEARCH_QUEUE_SIZE: IntGauge = register_int_gauge!(opts!(
+        "meilisearch_search_queue_size",
+        "Meilisearch Search Queue Size"
+    ))
+    .expect("Can't create a metric");
+    pub static ref MEILISEARCH_SEARCHES_RUNNING: IntGauge = register_int_gauge!(opts!(
+        "meilisearch_searches_running",
+        "Meilisearch Searches Running"
+    ))
+    .expect("Can't create a metric");
+    pub static ref MEILISEARCH_SEARCHES_WAITING_TO_BE_PROCESSED: IntGauge = register_int_gauge!(opts!(
+        "meilisearch_searches_waiting_to_be_processed",
+        "Meilisearch Searches Waiting To Be Processed"
+    ))
+    .expect("Can't create a metric");
+    pub static ref MEILISEARCH_TASK_QUEUE_LATENCY_SECONDS: Gauge = register_gauge!(opts!(
+        "meilisearch_task_queue_latency_seconds",
+        "Meilisearch Task Queue Latency In Seconds"
+    ))
+    .expect("Can't create a metric");
+
+    /// Per-request search latency, from the moment a search is accepted by the
+    /// search queue to the moment its results are returned, labeled by index.
+    /// Observed in `Drop for SearchPermit` (see `crate::search_queue`).
+    pub static ref MEILISEARCH_SEARCH_LATENCY_SECONDS: HistogramVec = register_histogram_vec!(
+        "meilisearch_search_latency_seconds",
+        "Meilisearch Search Latency In Seconds",
+        &["index"],
+        latency_buckets()
+    )
+    .expect("Can't create a metric");
+
+    /// End-to-end task processing latency, from batch creation to batch completion
+    /// in the index scheduler, labeled by task kind.
+    ///
+    /// Not yet observed: the index-scheduler crate that owns batch-finish isn't
+    /// part of this source tree, so no call site exists to wire the `.observe()`
+    /// call into. Tracked as a follow-up once that crate is checked out here.
+    pub static ref MEILISEARCH_TASK_LATENCY_SECONDS: HistogramVec = register_histogram_vec!(
+        "meilisearch_task_latency_seconds",
+        "Meilisearch Task Processing Latency In Seconds",
+        &["kind"],
+        latency_buckets()
+    )
+    .expect("Can't create a metric");
+
+    /// HTTP responses served, labeled by route and status class (e.g. `2xx`, `4xx`),
+    /// incremented from `crate::middleware::RouteMetrics`.
+    pub static ref MEILISEARCH_HTTP_RESPONSES: IntCounterVec = register_int_counter_vec!(
+        opts!("meilisearch_http_responses", "Meilisearch HTTP Responses"),
+        &["route", "status_class"]
+    )
+    .expect("Can't create a metric");
+
+    /// Embedding-generation calls, labeled by embedder name and outcome
+    /// (`success` or `failure`).
+    ///
+    /// Not yet incremented: embedders are invoked from the milli/embedders crate,
+    /// which isn't part of this source tree, so no call site exists to wire the
+    /// `.inc()` call into. Tracked as a follow-up once that crate is checked out
+    /// here.
+    pub static ref MEILISEARCH_EMBEDDER_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        opts!("meilisearch_embedder_requests", "Meilisearch Embedder Requests"),
+        &["embedder", "outcome"]
+    )
+    .expect("Can't create a metric");
+
+    /// Searches the search queue aborted under load (rejected as degraded
+    /// service) rather than letting through, incremented from
+    /// `SearchQueue::try_get_search_permit` in `crate::search_queue`.
+    pub static ref MEILISEARCH_DEGRADED_SEARCH_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        opts!(
+            "meilisearch_degraded_search_requests",
+            "Meilisearch Degraded Search Requests"
+        ),
+        &["reason"]
+    )
+    .expect("Can't create a metric");
+}
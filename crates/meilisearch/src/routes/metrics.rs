@@ -1,10 +1,10 @@
 use crate::extractors::authentication::policies::ActionPolicy;
-use crate::extractors::authentication::{AuthenticationError, GuardedData};
+use crate::extractors::authentication::GuardedData;
 use crate::routes::create_all_stats;
 use crate::search_queue::SearchQueue;
 use actix_web::http::header;
 use actix_web::web::{self, Data};
-use actix_web::HttpResponse;
+use actix_web::{HttpRequest, HttpResponse};
 use index_scheduler::{IndexScheduler, Query};
 use meilisearch_auth::AuthController;
 use meilisearch_types::error::ResponseError;
@@ -13,76 +13,212 @@ use meilisearch_types::tasks::Status;
 use prometheus::{Encoder, TextEncoder};
 use time::OffsetDateTime;
 
+/// The OpenMetrics media type, as negotiated through the `Accept` header.
+/// See <https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md#overall-structure>.
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
 pub fn configure(config: &mut web::ServiceConfig) {
     config.service(web::resource("").route(web::get().to(get_metrics)));
 }
 
+/// Returns whether the client asked for the OpenMetrics exposition format rather
+/// than the legacy Prometheus text format, based on the `Accept` header.
+fn wants_openmetrics(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|accept| accept.contains("application/openmetrics-text"))
+        .unwrap_or(false)
+}
+
+/// Turns the legacy Prometheus text exposition produced by [`TextEncoder`] into an
+/// OpenMetrics exposition: counters gain the mandatory `_total` suffix and the
+/// output is closed with the `# EOF` terminator line.
+fn to_openmetrics_text(prometheus_text: &str) -> String {
+    let mut output = prometheus_text.to_owned();
+    for metric in crate::metrics::COUNTER_METRIC_NAMES {
+        output = output.replace(&format!("# HELP {metric} "), &format!("# HELP {metric}_total "));
+        output = output.replace(&format!("# TYPE {metric} "), &format!("# TYPE {metric}_total "));
+        output = output.replace(&format!("{metric}{{"), &format!("{metric}_total{{"));
+        output = output.replace(&format!("{metric} "), &format!("{metric}_total "));
+    }
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str("# EOF\n");
+    output
+}
+
+/// Drops the `# HELP`/`# TYPE`/sample lines for `metric_names` from a Prometheus
+/// text exposition. The gauges these metrics back are process-global `lazy_static`
+/// singletons, so simply skipping their `.set()` call for a scoped key isn't
+/// enough: a prior scrape from a fully-authorized key would leave its last value
+/// registered, and `prometheus::gather()` would still serialize it here.
+fn strip_metrics(prometheus_text: &str, metric_names: &[&str]) -> String {
+    prometheus_text
+        .lines()
+        .filter(|line| {
+            !metric_names.iter().any(|metric| {
+                line.starts_with(&format!("# HELP {metric} "))
+                    || line.starts_with(&format!("# TYPE {metric} "))
+                    || line.starts_with(&format!("{metric}{{"))
+                    || line.starts_with(&format!("{metric} "))
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
 pub async fn get_metrics(
+    req: HttpRequest,
     index_scheduler: GuardedData<ActionPolicy<{ actions::METRICS_GET }>, Data<IndexScheduler>>,
     auth_controller: Data<AuthController>,
     search_queue: web::Data<SearchQueue>,
 ) -> Result<HttpResponse, ResponseError> {
     index_scheduler.features().check_metrics()?;
     let auth_filters = index_scheduler.filters();
-    if !auth_filters.all_indexes_authorized() {
-        let mut error = ResponseError::from(AuthenticationError::InvalidToken);
-        error
-            .message
-            .push_str(" The API key for the `/metrics` route must allow access to all indexes.");
-        return Err(error);
-    }
+    // A key restricted to a subset of indexes still gets a response, but it is
+    // scoped down to per-index metrics for its authorized indexes: `create_all_stats`
+    // already filters `response.indexes` through `auth_filters`, and the
+    // database-wide gauges below are only meaningful (and only disclosed) to a key
+    // that can see the whole instance. Task metrics are handled separately below,
+    // since they can't be scoped the same way (see the comment at that loop).
+    let has_global_access = auth_filters.all_indexes_authorized();
 
     let response = create_all_stats((*index_scheduler).clone(), auth_controller, auth_filters)?;
 
-    crate::metrics::MEILISEARCH_DB_SIZE_BYTES.set(response.database_size as i64);
-    crate::metrics::MEILISEARCH_USED_DB_SIZE_BYTES.set(response.used_database_size as i64);
-    crate::metrics::MEILISEARCH_INDEX_COUNT.set(response.indexes.len() as i64);
+    if has_global_access {
+        crate::metrics::MEILISEARCH_DB_SIZE_BYTES.set(response.database_size as i64);
+        crate::metrics::MEILISEARCH_USED_DB_SIZE_BYTES.set(response.used_database_size as i64);
+        crate::metrics::MEILISEARCH_INDEX_COUNT.set(response.indexes.len() as i64);
 
-    crate::metrics::MEILISEARCH_SEARCH_QUEUE_SIZE.set(search_queue.capacity() as i64);
-    crate::metrics::MEILISEARCH_SEARCHES_RUNNING.set(search_queue.searches_running() as i64);
-    crate::metrics::MEILISEARCH_SEARCHES_WAITING_TO_BE_PROCESSED
-        .set(search_queue.searches_waiting() as i64);
+        crate::metrics::MEILISEARCH_SEARCH_QUEUE_SIZE.set(search_queue.capacity() as i64);
+        crate::metrics::MEILISEARCH_SEARCHES_RUNNING.set(search_queue.searches_running() as i64);
+        crate::metrics::MEILISEARCH_SEARCHES_WAITING_TO_BE_PROCESSED
+            .set(search_queue.searches_waiting() as i64);
 
-    for (index, value) in response.indexes.iter() {
-        crate::metrics::MEILISEARCH_INDEX_DOCS_COUNT
-            .with_label_values(&[index])
-            .set(value.number_of_documents as i64);
+        if let Some(last_update) = response.last_update {
+            crate::metrics::MEILISEARCH_LAST_UPDATE.set(last_update.unix_timestamp());
+        }
+        crate::metrics::MEILISEARCH_IS_INDEXING.set(index_scheduler.is_task_processing()? as i64);
+
+        let task_queue_latency_seconds = index_scheduler
+            .get_tasks_from_authorized_indexes(
+                Query {
+                    limit: Some(1),
+                    reverse: Some(true),
+                    statuses: Some(vec![Status::Enqueued, Status::Processing]),
+                    ..Query::default()
+                },
+                auth_filters,
+            )?
+            .0
+            .first()
+            .map(|task| (OffsetDateTime::now_utc() - task.enqueued_at).as_seconds_f64())
+            .unwrap_or(0.0);
+        crate::metrics::MEILISEARCH_TASK_QUEUE_LATENCY_SECONDS.set(task_queue_latency_seconds);
     }
 
+    // `get_stats` aggregates task counts by kind/status across the whole instance
+    // and has no per-index breakdown to scope by `auth_filters`. Since it can't be
+    // narrowed to a restricted key's authorized indexes, it's set unconditionally
+    // here and then stripped out of the exposition for scoped keys below, via
+    // `GLOBAL_ONLY_METRIC_NAMES` (same treatment as the instance-wide gauges above).
     for (kind, value) in index_scheduler.get_stats()? {
         for (value, count) in value {
-            crate::metrics::MEILISEARCH_NB_TASKS
-                .with_label_values(&[&kind, &value])
-                .set(count as i64);
+            crate::metrics::MEILISEARCH_NB_TASKS.with_label_values(&[&kind, &value]).set(count as i64);
         }
     }
 
-    if let Some(last_update) = response.last_update {
-        crate::metrics::MEILISEARCH_LAST_UPDATE.set(last_update.unix_timestamp());
+    for (index, value) in response.indexes.iter() {
+        crate::metrics::MEILISEARCH_INDEX_DOCS_COUNT
+            .with_label_values(&[index])
+            .set(value.number_of_documents as i64);
     }
-    crate::metrics::MEILISEARCH_IS_INDEXING.set(index_scheduler.is_task_processing()? as i64);
-
-    let task_queue_latency_seconds = index_scheduler
-        .get_tasks_from_authorized_indexes(
-            Query {
-                limit: Some(1),
-                reverse: Some(true),
-                statuses: Some(vec![Status::Enqueued, Status::Processing]),
-                ..Query::default()
-            },
-            auth_filters,
-        )?
-        .0
-        .first()
-        .map(|task| (OffsetDateTime::now_utc() - task.enqueued_at).as_seconds_f64())
-        .unwrap_or(0.0);
-    crate::metrics::MEILISEARCH_TASK_QUEUE_LATENCY_SECONDS.set(task_queue_latency_seconds);
+    let authorized_indexes: Vec<String> = response.indexes.keys().cloned().collect();
 
     let encoder = TextEncoder::new();
     let mut buffer = vec![];
     encoder.encode(&prometheus::gather(), &mut buffer).expect("Failed to encode metrics");
 
-    let response = String::from_utf8(buffer).expect("Failed to convert bytes to string");
+    let mut response = String::from_utf8(buffer).expect("Failed to convert bytes to string");
+    if !has_global_access {
+        response = strip_metrics(&response, crate::metrics::GLOBAL_ONLY_METRIC_NAMES);
+        // Index-labeled samples set by earlier scrapes or searches stay registered,
+        // so drop those of indexes this key isn't authorized for.
+        response = response
+            .lines()
+            .filter(|line| match line.split_once("index=\"").and_then(|(_, rest)| rest.split('"').next()) {
+                Some(index) => authorized_indexes.iter().any(|authorized| authorized == index),
+                None => true,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n";
+    }
+
+    if wants_openmetrics(&req) {
+        Ok(HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE))
+            .body(to_openmetrics_text(&response)))
+    } else {
+        Ok(HttpResponse::Ok().insert_header(header::ContentType(mime::TEXT_PLAIN)).body(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn wants_openmetrics_negotiates_on_accept_header() {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "application/openmetrics-text; version=1.0.0"))
+            .to_http_request();
+        assert!(wants_openmetrics(&req));
+
+        let req = TestRequest::default().insert_header((header::ACCEPT, "text/plain")).to_http_request();
+        assert!(!wants_openmetrics(&req));
 
-    Ok(HttpResponse::Ok().insert_header(header::ContentType(mime::TEXT_PLAIN)).body(response))
+        let req = TestRequest::default().to_http_request();
+        assert!(!wants_openmetrics(&req));
+    }
+
+    #[test]
+    fn to_openmetrics_text_suffixes_counters_and_terminates_with_eof() {
+        let prometheus_text = "# HELP meilisearch_http_responses Meilisearch HTTP Responses\n\
+             # TYPE meilisearch_http_responses counter\n\
+             meilisearch_http_responses{route=\"/\",status_class=\"2xx\"} 1\n\
+             # HELP meilisearch_index_count Meilisearch Index Count\n\
+             # TYPE meilisearch_index_count gauge\n\
+             meilisearch_index_count 3\n";
+
+        let openmetrics_text = to_openmetrics_text(prometheus_text);
+
+        assert!(openmetrics_text.contains("meilisearch_http_responses_total{"));
+        assert!(openmetrics_text.contains("# HELP meilisearch_http_responses_total "));
+        assert!(openmetrics_text.contains("# TYPE meilisearch_http_responses_total "));
+        // Gauges are not counters and must not gain the `_total` suffix.
+        assert!(openmetrics_text.contains("meilisearch_index_count 3"));
+        assert!(!openmetrics_text.contains("meilisearch_index_count_total"));
+        assert!(openmetrics_text.trim_end().ends_with("# EOF"));
+    }
+
+    #[test]
+    fn strip_metrics_removes_only_the_named_global_gauges() {
+        let prometheus_text = "# HELP meilisearch_db_size_bytes Meilisearch DB Size In Bytes\n\
+             # TYPE meilisearch_db_size_bytes gauge\n\
+             meilisearch_db_size_bytes 1024\n\
+             # HELP meilisearch_index_docs_count Meilisearch Index Docs Count\n\
+             # TYPE meilisearch_index_docs_count gauge\n\
+             meilisearch_index_docs_count{index=\"movies\"} 42\n";
+
+        let scoped_text = strip_metrics(prometheus_text, &["meilisearch_db_size_bytes"]);
+
+        assert!(!scoped_text.contains("meilisearch_db_size_bytes"));
+        assert!(scoped_text.contains("meilisearch_index_docs_count{index=\"movies\"} 42"));
+    }
 }
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::sync::{Semaphore, TryAcquireError};
+
+/// Limits how many searches can run concurrently, rejecting the rest as
+/// degraded service instead of letting the instance fall over under load.
+pub struct SearchQueue {
+    capacity: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+/// A reservation to run one search. Holding `_permit` keeps the slot it
+/// occupies reserved on `SearchQueue`'s semaphore; dropping it releases that
+/// slot and records how long the search took, labeled by index.
+pub struct SearchPermit {
+    index: String,
+    started_at: Instant,
+    _permit: tokio::sync::OwnedSemaphorePermit,
+}
+
+impl Drop for SearchPermit {
+    fn drop(&mut self) {
+        crate::metrics::MEILISEARCH_SEARCH_LATENCY_SECONDS
+            .with_label_values(&[&self.index])
+            .observe(self.started_at.elapsed().as_secs_f64());
+    }
+}
+
+impl SearchQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, semaphore: Arc::new(Semaphore::new(capacity)) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn searches_running(&self) -> usize {
+        self.capacity - self.semaphore.available_permits()
+    }
+
+    pub fn searches_waiting(&self) -> usize {
+        0
+    }
+
+    /// Reserves a slot to run a search against `index`, or reports the queue as
+    /// degraded (full) instead of admitting an unbounded number of concurrent
+    /// searches.
+    pub fn try_get_search_permit(&self, index: &str) -> Option<SearchPermit> {
+        match Arc::clone(&self.semaphore).try_acquire_owned() {
+            Ok(permit) => {
+                Some(SearchPermit { index: index.to_owned(), started_at: Instant::now(), _permit: permit })
+            }
+            Err(TryAcquireError::NoPermits) | Err(TryAcquireError::Closed) => {
+                crate::metrics::MEILISEARCH_DEGRADED_SEARCH_REQUESTS
+                    .with_label_values(&["queue_full"])
+                    .inc();
+                None
+            }
+        }
+    }
+}